@@ -1,6 +1,7 @@
 use predictionentry::PredictionEntry;
 use simplemodel::SimpleWordTrainer;
 use simplemodel::SimpleWordPredictor;
+use tokenizer::{Tokenizer, DefaultTokenizer};
 
 use std::old_io::File;
 use std::old_io::BufferedReader;
@@ -9,6 +10,12 @@ use std::collections::HashMap;
 use std::collections::hash_map::Entry;
 
 /// Bigram prediction trainer.
+///
+/// Raw input passed to `train_str` or `train_vec` is tokenized by a
+/// `Tokenizer`, which defaults to `DefaultTokenizer` but can be
+/// swapped via `with_tokenizer`, the same way `SimpleWordTrainer`
+/// does, so bigram contexts are keyed by the same folded, punctuation
+/// stripped words the unigram model trains on.
 pub struct BigramTrainer {
     // The collection of all position 1 words in the bigram.
     outer_map: HashMap<String, SimpleWordTrainer>,
@@ -16,24 +23,36 @@ pub struct BigramTrainer {
     // For creating a bigram from the first word of a new .train()
     // call.
     prev_word: Option<String>,
+
+    tokenizer: Box<Tokenizer>,
 }
 
 impl BigramTrainer {
-    /// Creates a new, empty BigramTrainer.
+    /// Creates a new, empty BigramTrainer using the default tokenizer.
     pub fn new() -> BigramTrainer {
-        let model_hm = HashMap::new();
+        BigramTrainer::with_tokenizer(Box::new(DefaultTokenizer::new()))
+    }
 
-        BigramTrainer {outer_map: model_hm, prev_word: None}
+    /// Creates a new, empty BigramTrainer that tokenizes input with a
+    /// caller-supplied `Tokenizer`.
+    pub fn with_tokenizer(tokenizer: Box<Tokenizer>) -> BigramTrainer {
+        BigramTrainer {outer_map: HashMap::new(), prev_word: None, tokenizer: tokenizer}
     }
 
-    /// Train the model on a vector of individual words.
+    /// Train the model on a vector of individual words, tokenized by
+    /// `self.tokenizer` as if they were joined back into one string.
     pub fn train_vec(&mut self, input: Vec<&str>) {
-        count_words(self, &input);
+        self.train_str(&input.join(" "));
     }
 
-    /// Train the model on a str that will be split in to words.
+    /// Train the model on a string, tokenized by `self.tokenizer`.
     pub fn train_str(&mut self, input: &str) {
-        let v_input = input.split(' ').collect();
+        let words = self.tokenizer.tokenize(input);
+        if words.is_empty() {
+            return;
+        }
+
+        let v_input: Vec<&str> = words.iter().map(|w| w.as_slice()).collect();
         count_words(self, &v_input);
     }
 
@@ -54,6 +73,8 @@ impl BigramTrainer {
 pub struct BigramPredictor(HashMap<String, SimpleWordPredictor>);
 
 impl BigramPredictor {
+    /// Save bigram counts to a CSV file, one row per (prev, word)
+    /// pair: `prev,word,score`.
     pub fn to_file(&self, path: &Path) {
         let mut file = File::create(path);
         let BigramPredictor(ref hm) = *self;
@@ -63,13 +84,36 @@ impl BigramPredictor {
         for word in keys {
             let inner_entries = hm.get(word).unwrap().scores();
             for entry in inner_entries {
-                write!(&mut file, "{} {},{}\n", word, entry.word, entry.score)
+                write!(&mut file, "{},{},{}\n", word, entry.word, entry.score)
                     .unwrap();
             }
         }
 
     }
 
+    /// Load bigram counts from a CSV file written by `to_file`.
+    pub fn from_file(path: &Path) -> BigramPredictor {
+        let mut trainer = BigramTrainer::new();
+        let mut file = BufferedReader::new(File::open(path));
+
+        for line_res in file.lines() {
+            let line = line_res.unwrap();
+            let line = line.trim();
+            let fields: Vec<&str> = line.split(',').collect();
+            let prev = fields[0];
+            let word = fields[1];
+            let score: u32 = fields[2].parse().ok().unwrap();
+
+            let inner_trainer = trainer.outer_map.entry(String::from_str(prev))
+                .or_insert_with(SimpleWordTrainer::new);
+            for _ in 0..score {
+                inner_trainer.train_word(word);
+            }
+        }
+
+        trainer.finalize()
+    }
+
     pub fn predict(&self, word1: &str, letters: &str) -> Vec<PredictionEntry> {
         let simplemodel = self.get_simplemodel(word1);
 
@@ -79,11 +123,33 @@ impl BigramPredictor {
         }
     }
 
+    /// Predict the word following `word1` filtered to the `partial`
+    /// prefix, falling back to `unigram`'s unigram-frequency
+    /// prediction when `word1` was never seen as a bigram context.
+    pub fn predict_next(&self, word1: &str, partial: &str, unigram: &SimpleWordPredictor) -> Vec<PredictionEntry> {
+        let predictions = self.predict(word1, partial);
+
+        if predictions.is_empty() {
+            unigram.predict(partial)
+        } else {
+            predictions
+        }
+    }
+
     fn get_simplemodel(&self, word1: &str) -> Option<&SimpleWordPredictor>{
         let &BigramPredictor(ref hm) = self;
 
         hm.get(&String::from_str(word1))
     }
+
+    /// Record that `word2` followed `word1`, incrementing that
+    /// bigram's score in place without rebuilding the model.
+    pub fn observe(&mut self, word1: &str, word2: &str) {
+        let &mut BigramPredictor(ref mut hm) = self;
+        let inner = hm.entry(String::from_str(word1))
+            .or_insert_with(|| SimpleWordTrainer::new().finalize());
+        inner.observe(word2);
+    }
 }
 
 fn count_words(trainer: &mut BigramTrainer, input: &Vec<&str>) {
@@ -129,6 +195,18 @@ mod tests {
         inner_trainer.debug_get_word_score(word2).unwrap().clone()
     }
 
+    #[test]
+    fn test_train_str_runs_through_tokenizer() {
+        let mut model = BigramTrainer::new();
+
+        model.train_str("The Cat, sat on THE mat.");
+
+        // Case folded and punctuation stripped, the same way
+        // SimpleWordTrainer's tokenizer would have handled it.
+        assert_eq!(1, get_score(&model, "the", "cat"));
+        assert_eq!(1, get_score(&model, "on", "the"));
+    }
+
     #[test]
     fn test_from_str() {
         let mut model = BigramTrainer::new();
@@ -147,4 +225,40 @@ mod tests {
         assert_eq!(1, get_score(&model, "dundee", "joe"));
         assert_eq!(2, get_score(&model, "happy", "happy"));
     }
+
+    #[test]
+    fn test_predict_next_falls_back_to_unigram() {
+        use simplemodel::SimpleWordTrainer as UnigramTrainer;
+
+        let mut model = BigramTrainer::new();
+        model.train_str("the cat sat on the mat");
+        let bigram = model.finalize();
+
+        let mut unigram_trainer = UnigramTrainer::new();
+        unigram_trainer.train_str("the cat sat on the mat");
+        let unigram = unigram_trainer.finalize();
+
+        // "the" has a seen bigram context ("the" -> "cat"/"mat"), so
+        // predict_next should use it rather than the unigram fallback.
+        let seen = bigram.predict_next("the", "c", &unigram);
+        assert_eq!(seen[0].word, "cat");
+
+        // "mat" was never followed by anything, so there's no bigram
+        // context for it; predict_next should fall back to unigram.
+        let unseen = bigram.predict_next("mat", "s", &unigram);
+        assert_eq!(unseen[0].word, "sat");
+    }
+
+    #[test]
+    fn test_observe() {
+        let mut model = BigramTrainer::new();
+        model.train_str("the cat sat");
+
+        let mut predictor = model.finalize();
+        predictor.observe("the", "dog");
+        predictor.observe("never", "seen");
+
+        assert_eq!(predictor.predict("the", "d")[0].word, "dog");
+        assert_eq!(predictor.predict("never", "s")[0].word, "seen");
+    }
 }
@@ -2,20 +2,113 @@
 extern crate autocomplete;
 
 use std::old_io;
+use std::old_io::File;
+use std::old_io::BufferedReader;
+use std::env;
 
-use autocomplete::simplemodel::SimpleWordPredictor;
+use autocomplete::simplemodel::{SimpleWordTrainer, SimpleWordPredictor};
+use autocomplete::eval::{evaluate, split_corpus};
 
 fn main() {
+    let args: Vec<String> = env::args().collect();
+
+    if args.len() > 1 && args[1].as_slice() == "eval" {
+        run_eval();
+    } else {
+        run_repl();
+    }
+}
+
+fn run_repl() {
     println!("Loading training data.");
-    let predictor = SimpleWordPredictor::from_file(&Path::new("training_data.csv"));
+    let path = Path::new("training_data.csv");
+    let mut predictor = SimpleWordPredictor::from_file(&path);
+    let mut stdin = old_io::stdin();
+
     loop {
         print!("Input: ");
-        let input = old_io::stdin().read_line().ok().expect("Failed to read line.");
-        let output = predictor.predict(input.trim());
+        let input = match stdin.read_line() {
+            Ok(line) => line,
+            Err(_) => break,
+        };
+        let input = input.trim();
+
+        if input == ":save" {
+            predictor.to_file(&path);
+            println!("Saved.");
+            continue;
+        }
+
+        let output = predictor.predict(input);
         println!("Score\tWord");
-        for entry in output {
-            println!("{}\t{}", entry.score, entry.word);
-         }
+        for (ix, entry) in output.iter().enumerate() {
+            println!("{}: {}\t{}", ix, entry.score, entry.word);
+        }
+
+        print!("Accept (index, full word, or blank to skip): ");
+        let choice = match stdin.read_line() {
+            Ok(line) => line,
+            Err(_) => break,
+        };
+        let choice = choice.trim();
+
+        let accepted = match choice.parse::<usize>() {
+            Ok(ix) => output.get(ix).map(|entry| entry.word.clone()),
+            Err(_) if choice.len() > 0 => Some(String::from_str(choice)),
+            Err(_) => None,
+        };
 
+        if let Some(word) = accepted {
+            predictor.observe(&word);
+        }
     }
+
+    // EOF on stdin (closed pipe, finite input file, Ctrl-D) ends the
+    // session and saves the model the same way `:save` does, so a
+    // session never loses an observe()'d correction just because
+    // stdin ran out.
+    predictor.to_file(&path);
+    println!("Saved.");
+}
+
+// Loads training_data.csv as a corpus, splits it into train/test sets,
+// trains a fresh SimpleWordPredictor on the train split, and prints
+// the resulting EvalReport for the test split.
+fn run_eval() {
+    let sentences = load_corpus(&Path::new("training_data.csv"));
+    let (train, test) = split_corpus(sentences, 0.1);
+
+    let mut trainer = SimpleWordTrainer::new();
+    for sentence in &train {
+        let words: Vec<&str> = sentence.iter().map(|w| w.as_slice()).collect();
+        trainer.train_vec(words);
+    }
+    let predictor = trainer.finalize();
+
+    let report = evaluate(&test, |_context, prefix| predictor.predict(prefix));
+
+    println!("top1:  {:.3}", report.top1);
+    println!("top3:  {:.3}", report.top3);
+    println!("top10: {:.3}", report.top10);
+    println!("mrr:   {:.3}", report.mrr);
+    println!("mean keystrokes saved: {:.3}", report.mean_keystrokes_saved);
+}
+
+// training_data.csv is a "word,score" CSV rather than prose, so each
+// row is treated as its own one-word sentence for evaluation.
+fn load_corpus(path: &Path) -> Vec<Vec<String>> {
+    let mut file = BufferedReader::new(File::open(path));
+    let mut sentences = Vec::new();
+
+    for line_res in file.lines() {
+        let line = line_res.unwrap();
+        let line = line.trim();
+        if let Some(word) = line.split(',').next() {
+            if word.len() > 0 {
+                sentences.push(vec![String::from_str(word)]);
+            }
+        }
+    }
+
+    sentences
 }
@@ -0,0 +1,147 @@
+use predictionentry::PredictionEntry;
+
+/// Aggregate accuracy of a predictor over a held-out corpus, the way
+/// word-game solvers are benchmarked: for every word, how few
+/// keystrokes would a user have had to type before the predictor
+/// surfaced the right word.
+#[derive(Debug, Clone, PartialEq)]
+pub struct EvalReport {
+    /// Fraction of words whose first top-k appearance was rank 1.
+    pub top1: f64,
+    /// Fraction of words whose first top-k appearance was rank <= 3.
+    pub top3: f64,
+    /// Fraction of words that appeared in the top-10 list at all.
+    pub top10: f64,
+    /// Mean reciprocal rank, 0 for words never predicted.
+    pub mrr: f64,
+    /// Mean number of characters a user didn't have to type, i.e.
+    /// word length minus the prefix length at which the word first
+    /// entered the top-10 list.
+    pub mean_keystrokes_saved: f64,
+}
+
+/// Evaluate `predict` against `sentences`. `predict` is handed the
+/// words typed so far in the current sentence (the context) and the
+/// prefix typed of the word currently being completed, and should
+/// return its top-10 `PredictionEntry` list, exactly like the
+/// predictors in this crate do.
+pub fn evaluate<F>(sentences: &[Vec<String>], mut predict: F) -> EvalReport
+    where F: FnMut(&[&str], &str) -> Vec<PredictionEntry>
+{
+    let mut word_count = 0u32;
+    let mut top1_hits = 0u32;
+    let mut top3_hits = 0u32;
+    let mut top10_hits = 0u32;
+    let mut reciprocal_rank_sum = 0f64;
+    let mut keystrokes_saved_sum = 0f64;
+
+    for sentence in sentences {
+        for (ix, word) in sentence.iter().enumerate() {
+            let context: Vec<&str> = sentence[..ix].iter().map(|w| w.as_slice()).collect();
+            word_count += 1;
+
+            let chars: Vec<char> = word.chars().collect();
+            let mut found = None;
+            for prefix_len in 1..chars.len() + 1 {
+                let prefix: String = chars[..prefix_len].iter().cloned().collect();
+                let predictions = predict(&context, prefix.as_slice());
+                let rank = predictions.iter().position(|e| &e.word == word);
+
+                if let Some(ix) = rank {
+                    found = Some((prefix_len, ix + 1));
+                    break;
+                }
+            }
+
+            if let Some((prefix_len, rank)) = found {
+                if rank == 1 {
+                    top1_hits += 1;
+                }
+                if rank <= 3 {
+                    top3_hits += 1;
+                }
+                top10_hits += 1;
+                reciprocal_rank_sum += 1.0 / rank as f64;
+                keystrokes_saved_sum += (chars.len() - prefix_len) as f64;
+            }
+        }
+    }
+
+    let total = word_count as f64;
+    EvalReport {
+        top1: top1_hits as f64 / total,
+        top3: top3_hits as f64 / total,
+        top10: top10_hits as f64 / total,
+        mrr: reciprocal_rank_sum / total,
+        mean_keystrokes_saved: keystrokes_saved_sum / total,
+    }
+}
+
+/// Split a corpus (one sentence per element, each pre-tokenized into
+/// words) into a training set and a held-out test set. `test_frac` is
+/// the fraction, from 0.0 to 1.0, of sentences held out for testing;
+/// every `1 / test_frac`-th sentence is held out.
+pub fn split_corpus(sentences: Vec<Vec<String>>, test_frac: f64) -> (Vec<Vec<String>>, Vec<Vec<String>>) {
+    let stride = (1.0 / test_frac).round() as usize;
+    let stride = if stride == 0 { 1 } else { stride };
+
+    let mut train = Vec::new();
+    let mut test = Vec::new();
+
+    for (ix, sentence) in sentences.into_iter().enumerate() {
+        if stride > 0 && (ix + 1) % stride == 0 {
+            test.push(sentence);
+        } else {
+            train.push(sentence);
+        }
+    }
+
+    (train, test)
+}
+
+#[cfg(test)]
+mod tests {
+    use eval::{evaluate, split_corpus};
+    use predictionentry::PredictionEntry;
+
+    #[test]
+    fn test_evaluate_perfect_predictor() {
+        let sentences = vec![vec![String::from_str("hello"), String::from_str("world")]];
+
+        let report = evaluate(&sentences, |_context, prefix| {
+            ["hello", "world"].iter()
+                .filter(|w| w.starts_with(prefix))
+                .map(|w| PredictionEntry {word: String::from_str(*w), score: 1})
+                .collect()
+        });
+
+        assert_eq!(report.top1, 1.0);
+        assert_eq!(report.mrr, 1.0);
+        // "hello" (5 chars) and "world" (5 chars) both hit on a
+        // 1-character prefix, so 4 keystrokes are saved on average.
+        assert_eq!(report.mean_keystrokes_saved, 4.0);
+    }
+
+    #[test]
+    fn test_evaluate_predictor_that_never_matches() {
+        let sentences = vec![vec![String::from_str("hello")]];
+
+        let report = evaluate(&sentences, |_context, _prefix| Vec::new());
+
+        assert_eq!(report.top1, 0.0);
+        assert_eq!(report.mrr, 0.0);
+        assert_eq!(report.mean_keystrokes_saved, 0.0);
+    }
+
+    #[test]
+    fn test_split_corpus() {
+        let sentences: Vec<Vec<String>> = (0..10)
+            .map(|i| vec![i.to_string()])
+            .collect();
+
+        let (train, test) = split_corpus(sentences, 0.1);
+
+        assert_eq!(test.len(), 1);
+        assert_eq!(train.len(), 9);
+    }
+}
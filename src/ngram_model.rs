@@ -0,0 +1,608 @@
+use predictionentry::{PredictionEntry, ScoredPredictionEntry};
+use simplemodel::{SimpleWordTrainer, SimpleWordPredictor};
+use tokenizer::{Tokenizer, DefaultTokenizer};
+
+use std::old_io::File;
+use std::old_io::BufferedReader;
+use std::collections::HashMap;
+use std::collections::BinaryHeap;
+use std::collections::hash_map::Entry;
+use std::cmp::Ordering;
+
+/// Default stupid-backoff weight applied each time a shorter context
+/// is substituted for an unseen longer one.
+pub const DEFAULT_ALPHA: f64 = 0.4;
+
+/// N-gram prediction trainer.
+///
+/// Unlike BigramTrainer, which only ever tracks a two-word context,
+/// NgramTrainer keeps a count table for every context length from one
+/// word up to `order - 1` words, plus a unigram table, so prediction
+/// can fall back to shorter contexts on sparse data instead of
+/// failing outright.
+///
+/// Raw input passed to `train_str` is tokenized by a `Tokenizer`,
+/// which defaults to `DefaultTokenizer` but can be swapped via
+/// `with_tokenizer`, the same way `SimpleWordTrainer` and
+/// `BigramTrainer` do, so every context table is keyed by the same
+/// folded, punctuation-stripped words the unigram model trains on.
+pub struct NgramTrainer {
+    order: usize,
+    // tables[k - 1] holds counts for a context of k words: the
+    // context (its words joined by a single space) maps to a table of
+    // following word -> count.
+    tables: Vec<HashMap<String, HashMap<String, u32>>>,
+    unigram: SimpleWordTrainer,
+    history: Vec<String>,
+    tokenizer: Box<Tokenizer>,
+}
+
+impl NgramTrainer {
+    /// Create a new, empty trainer for n-grams up to `order` words
+    /// long, using the default tokenizer. `order` must be at least 2.
+    pub fn new(order: usize) -> NgramTrainer {
+        NgramTrainer::with_tokenizer(order, Box::new(DefaultTokenizer::new()))
+    }
+
+    /// Create a new, empty trainer for n-grams up to `order` words
+    /// long that tokenizes input with a caller-supplied `Tokenizer`.
+    /// `order` must be at least 2.
+    pub fn with_tokenizer(order: usize, tokenizer: Box<Tokenizer>) -> NgramTrainer {
+        let tables = (0..order - 1).map(|_| HashMap::new()).collect();
+
+        NgramTrainer {
+            order: order,
+            tables: tables,
+            unigram: SimpleWordTrainer::new(),
+            history: Vec::new(),
+            tokenizer: tokenizer,
+        }
+    }
+
+    /// Train the model on a str, tokenized by `self.tokenizer`.
+    ///
+    /// Unlike `SimpleWordTrainer::train_str` and
+    /// `BigramTrainer::train_str`, a trailing `.`, `!` or `?` on a
+    /// raw space-separated token survives tokenization instead of
+    /// being stripped: `complete_sentence`'s `ends_sentence` check
+    /// relies on that punctuation still being part of the trained
+    /// word to know where a sentence ends, so it's reattached to the
+    /// last sub-token the tokenizer produces for that word (normally
+    /// the only one, unless CJK splitting fans it out).
+    pub fn train_str(&mut self, input: &str) {
+        let words = tokenize_preserving_sentence_end(&*self.tokenizer, input);
+        let v_input: Vec<&str> = words.iter().map(|w| w.as_slice()).collect();
+        self.train_vec(v_input);
+    }
+
+    /// Train the model on a vector of individual words, continuing
+    /// any context left over from a previous call.
+    pub fn train_vec(&mut self, input: Vec<&str>) {
+        for word in input {
+            if word.len() == 0 {
+                continue;
+            }
+
+            self.unigram.train_word(word);
+
+            for ctx_len in 1..self.order {
+                if self.history.len() < ctx_len {
+                    continue;
+                }
+
+                let start = self.history.len() - ctx_len;
+                let context = self.history[start..].join(" ");
+                let counts = self.tables[ctx_len - 1].entry(context)
+                    .or_insert_with(HashMap::new);
+
+                match counts.entry(String::from_str(word)) {
+                    Entry::Vacant(e) => { e.insert(1); },
+                    Entry::Occupied(mut e) => { *e.get_mut() += 1; },
+                }
+            }
+
+            self.history.push(String::from_str(word));
+        }
+    }
+
+    /// Perform the calculations needed to predict with this model.
+    pub fn finalize(self) -> NgramPredictor {
+        NgramPredictor {
+            tables: self.tables,
+            alpha: DEFAULT_ALPHA,
+            unigram: self.unigram.finalize(),
+        }
+    }
+}
+
+/// N-gram prediction engine, scored with stupid backoff: the full
+/// `order - 1`-word context is preferred, falling back to each
+/// shorter context (multiplied by `alpha` per step) and finally to
+/// unigram frequency when no context has been seen.
+pub struct NgramPredictor {
+    tables: Vec<HashMap<String, HashMap<String, u32>>>,
+    alpha: f64,
+    unigram: SimpleWordPredictor,
+}
+
+impl NgramPredictor {
+    /// Override the stupid-backoff weight (default 0.4).
+    pub fn set_alpha(&mut self, alpha: f64) {
+        self.alpha = alpha;
+    }
+
+    /// Predict the next word given the preceding `context` (oldest
+    /// word first), filtered to candidates whose spelling starts with
+    /// `letters`, ranked by stupid-backoff score. Every prefix match
+    /// is scored before the top 10 are kept, so a word the context
+    /// strongly favors can still surface even if it isn't among the
+    /// globally most frequent completions of `letters`.
+    pub fn predict(&self, context: &[&str], letters: &str) -> Vec<PredictionEntry> {
+        let max_ctx = self.tables.len();
+        let trimmed: &[&str] = if context.len() > max_ctx {
+            &context[context.len() - max_ctx..]
+        } else {
+            context
+        };
+
+        let mut predictions: Vec<PredictionEntry> = self.unigram.predict_prefix_all(letters).into_iter()
+            .map(|entry| {
+                let score = self.backoff_score(trimmed, &entry.word);
+                PredictionEntry {word: entry.word, score: (score * 1_000_000.0) as u32}
+            })
+            .collect();
+
+        predictions.sort_by(|a, b| {
+            b.score.cmp(&a.score)
+        });
+
+        predictions.truncate(10);
+
+        predictions
+    }
+
+    /// Like `predict`, but combines the context's stupid-backoff
+    /// score with an add-k-smoothed unigram prior the way a naive
+    /// Bayes classifier accumulates evidence: in log space, so the
+    /// two sources of evidence multiply together instead of being
+    /// summed as raw counts.
+    pub fn predict_scored(&self, context: &[&str], letters: &str, k: f64) -> Vec<ScoredPredictionEntry> {
+        let max_ctx = self.tables.len();
+        let trimmed: &[&str] = if context.len() > max_ctx {
+            &context[context.len() - max_ctx..]
+        } else {
+            context
+        };
+
+        let unigram_total = self.unigram.total() as f64;
+        let vocab = self.unigram.scores().len() as f64;
+
+        let mut predictions: Vec<ScoredPredictionEntry> = self.unigram.predict_prefix_all(letters).into_iter()
+            .map(|entry| {
+                let context_prob = self.backoff_score(trimmed, &entry.word).max(1e-9);
+                let unigram_prior = (entry.score as f64 + k) / (unigram_total + k * vocab);
+                let log_prob = context_prob.ln() + unigram_prior.ln();
+
+                ScoredPredictionEntry {
+                    word: entry.word,
+                    score: entry.score,
+                    probability: log_prob.exp(),
+                }
+            })
+            .collect();
+
+        predictions.sort_by(|a, b| b.probability.partial_cmp(&a.probability).unwrap());
+        predictions.truncate(10);
+
+        predictions
+    }
+
+    // Stupid backoff: score against the longest context we have
+    // counts for, discounting by `alpha` each time we drop the oldest
+    // word of the context, bottoming out at the unigram frequency.
+    fn backoff_score(&self, context: &[&str], word: &str) -> f64 {
+        if context.is_empty() {
+            return self.unigram_prob(word);
+        }
+
+        let ctx_len = context.len();
+        if let Some(counts) = self.tables[ctx_len - 1].get(&context.join(" ")) {
+            let total: u32 = counts.values().sum();
+            if total > 0 {
+                return match counts.get(word) {
+                    Some(&c) => c as f64 / total as f64,
+                    None => self.alpha * self.backoff_score(&context[1..], word),
+                };
+            }
+        }
+
+        self.alpha * self.backoff_score(&context[1..], word)
+    }
+
+    fn unigram_prob(&self, word: &str) -> f64 {
+        let total = self.unigram.total();
+        if total == 0 {
+            return 0.0;
+        }
+
+        match self.unigram.score_of(word) {
+            Some(score) => score as f64 / total as f64,
+            None => 0.0,
+        }
+    }
+
+    /// Save every context table to a CSV file. Each row records its
+    /// context length so rows of different n-gram orders can share a
+    /// single file: `context_length,context,word,score`.
+    pub fn to_file(&self, path: &Path) {
+        let mut file = File::create(path);
+
+        for (ix, table) in self.tables.iter().enumerate() {
+            let ctx_len = ix + 1;
+            let mut contexts: Vec<&String> = table.keys().collect();
+            contexts.sort();
+
+            for context in contexts {
+                let counts = table.get(context).unwrap();
+                let mut words: Vec<&String> = counts.keys().collect();
+                words.sort();
+
+                for word in words {
+                    let score = counts.get(word).unwrap();
+                    write!(&mut file, "{},{},{},{}\n", ctx_len, context, word, score)
+                        .unwrap();
+                }
+            }
+        }
+    }
+
+    /// Beam-search a whole continuation rather than a single next
+    /// word: starting from `context` (the words typed so far, space
+    /// separated), keep the `width` best partial `Sequence`s by total
+    /// log-probability, expanding each one step at a time by its
+    /// top-`width` successor words (the n-gram counts for the longest
+    /// context we have data for, turned into a probability
+    /// distribution via softmax), until a sequence hits `max_len`
+    /// words or produces one ending in '.', '!' or '?'. Accumulating
+    /// log-probabilities rather than multiplying raw probabilities
+    /// avoids float underflow on long completions, and lets
+    /// completions of different lengths be compared on equal footing.
+    /// Returns every finished continuation and its log-probability,
+    /// best first.
+    pub fn complete_sentence(&self, context: &str, width: usize, max_len: usize) -> Vec<(String, f32)> {
+        let seed: Vec<String> = context.split(' ')
+            .filter(|w| w.len() > 0)
+            .map(String::from_str)
+            .collect();
+
+        let mut beams = vec![Sequence {words: Vec::new(), log_prob: 0.0}];
+        let mut finished = Vec::new();
+
+        for _ in 0..max_len {
+            if beams.is_empty() {
+                break;
+            }
+
+            let mut candidates = BinaryHeap::new();
+
+            for seq in beams {
+                let mut history = seed.clone();
+                history.extend(seq.words.iter().cloned());
+                let ctx: Vec<&str> = history.iter().map(|w| w.as_slice()).collect();
+
+                for (word, log_p) in self.top_successors(&ctx, width) {
+                    let mut words = seq.words.clone();
+                    let log_prob = seq.log_prob + log_p;
+                    let is_ending = ends_sentence(&word);
+                    words.push(word);
+
+                    if is_ending || words.len() >= max_len {
+                        finished.push((words.join(" "), log_prob));
+                    } else {
+                        candidates.push(Sequence {words: words, log_prob: log_prob});
+                    }
+                }
+            }
+
+            let mut ranked = candidates.into_sorted_vec();
+            ranked.reverse();
+            ranked.truncate(width);
+
+            beams = ranked;
+        }
+
+        finished.sort_by(|a, b| b.1.partial_cmp(&a.1).unwrap());
+
+        finished
+    }
+
+    // Find the candidate words following `context`, backing off to
+    // shorter contexts exactly as `backoff_score` does until a table
+    // with data is found (falling all the way back to unigram
+    // frequencies), take the top `width` by raw count, and turn those
+    // counts into a probability distribution via softmax so the beam
+    // search always has somewhere to go even when counts are tiny.
+    fn top_successors(&self, context: &[&str], width: usize) -> Vec<(String, f32)> {
+        let counts = self.successor_counts(context);
+
+        let mut ranked: Vec<(&String, &u32)> = counts.iter().collect();
+        ranked.sort_by(|a, b| b.1.cmp(a.1));
+        ranked.truncate(width);
+
+        let max = ranked.iter().map(|&(_, &c)| c).max().unwrap_or(0) as f32;
+        let exps: Vec<f32> = ranked.iter().map(|&(_, &c)| (c as f32 - max).exp()).collect();
+        let sum: f32 = exps.iter().sum();
+
+        ranked.iter().zip(exps.iter())
+            .map(|(&(word, _), &e)| (word.clone(), (e / sum).ln()))
+            .collect()
+    }
+
+    // The full following-word count table for the longest context
+    // we have data for, walking the same backoff chain `backoff_score`
+    // does instead of scoring a single word.
+    fn successor_counts(&self, context: &[&str]) -> HashMap<String, u32> {
+        let max_ctx = self.tables.len();
+        let mut ctx_len = if context.len() > max_ctx { max_ctx } else { context.len() };
+
+        while ctx_len > 0 {
+            let key = context[context.len() - ctx_len..].join(" ");
+            if let Some(counts) = self.tables[ctx_len - 1].get(&key) {
+                if !counts.is_empty() {
+                    return counts.clone();
+                }
+            }
+            ctx_len -= 1;
+        }
+
+        self.unigram.scores().into_iter().map(|e| (e.word, e.score)).collect()
+    }
+
+    /// Load context tables from a CSV file written by `to_file`, and
+    /// unigram frequencies from a `SimpleWordPredictor` CSV file.
+    pub fn from_file(ngram_path: &Path, unigram_path: &Path, order: usize) -> NgramPredictor {
+        let mut tables: Vec<HashMap<String, HashMap<String, u32>>> =
+            (0..order - 1).map(|_| HashMap::new()).collect();
+
+        let mut file = BufferedReader::new(File::open(ngram_path));
+        for line_res in file.lines() {
+            let line = line_res.unwrap();
+            let line = line.trim();
+            let fields: Vec<&str> = line.split(',').collect();
+            let ctx_len: usize = fields[0].parse().ok().unwrap();
+            let context = String::from_str(fields[1]);
+            let word = String::from_str(fields[2]);
+            let score: u32 = fields[3].parse().ok().unwrap();
+
+            let counts = tables[ctx_len - 1].entry(context).or_insert_with(HashMap::new);
+            counts.insert(word, score);
+        }
+
+        NgramPredictor {
+            tables: tables,
+            alpha: DEFAULT_ALPHA,
+            unigram: SimpleWordPredictor::from_file(unigram_path),
+        }
+    }
+}
+
+// A partial beam-search completion: the words generated so far and
+// the accumulated log-probability of generating exactly that
+// sequence. Ordered by `log_prob` so a `BinaryHeap<Sequence>` always
+// pops the best partial completion first.
+#[derive(Debug, Clone, PartialEq)]
+struct Sequence {
+    words: Vec<String>,
+    log_prob: f32,
+}
+
+impl Eq for Sequence {}
+
+impl PartialOrd for Sequence {
+    fn partial_cmp(&self, other: &Sequence) -> Option<Ordering> {
+        self.log_prob.partial_cmp(&other.log_prob)
+    }
+}
+
+impl Ord for Sequence {
+    fn cmp(&self, other: &Sequence) -> Ordering {
+        self.partial_cmp(other).unwrap_or(Ordering::Equal)
+    }
+}
+
+fn ends_sentence(word: &str) -> bool {
+    word.ends_with('.') || word.ends_with('!') || word.ends_with('?')
+}
+
+fn is_sentence_end_char(c: char) -> bool {
+    c == '.' || c == '!' || c == '?'
+}
+
+// Split `input` on spaces and run each token through `tokenizer`
+// (case folding, accent folding, CJK splitting, stop words) the same
+// way `SimpleWordTrainer` and `BigramTrainer` do, except a trailing
+// `.`, `!` or `?` is set aside first and reattached to the last
+// sub-token produced for that raw token, so `ends_sentence` can still
+// recognize a sentence boundary in the trained vocabulary.
+fn tokenize_preserving_sentence_end(tokenizer: &Tokenizer, input: &str) -> Vec<String> {
+    let mut words = Vec::new();
+
+    for raw in input.split(' ') {
+        if raw.len() == 0 {
+            continue;
+        }
+
+        let mut chars: Vec<char> = raw.chars().collect();
+        let mut suffix = String::new();
+        while let Some(&c) = chars.last() {
+            if is_sentence_end_char(c) {
+                suffix.insert(0, c);
+                chars.pop();
+            } else {
+                break;
+            }
+        }
+
+        let stripped: String = chars.into_iter().collect();
+        let mut tokens = tokenizer.tokenize(&stripped);
+
+        if tokens.is_empty() {
+            if suffix.len() > 0 {
+                words.push(suffix);
+            }
+            continue;
+        }
+
+        if suffix.len() > 0 {
+            let last = tokens.len() - 1;
+            tokens[last].push_str(&suffix);
+        }
+
+        words.extend(tokens);
+    }
+
+    words
+}
+
+#[cfg(test)]
+mod tests {
+    use ngram_model::NgramTrainer;
+
+    #[test]
+    fn test_train_vec_counts_every_context_length() {
+        let mut model = NgramTrainer::new(3);
+
+        model.train_str("a happy little dog chased a happy little cat");
+
+        assert_eq!(2, *model.tables[0].get("a").unwrap().get("happy").unwrap());
+        assert_eq!(2, *model.tables[0].get("happy").unwrap().get("little").unwrap());
+        assert_eq!(2, *model.tables[1].get("a happy").unwrap().get("little").unwrap());
+    }
+
+    #[test]
+    fn test_train_str_runs_through_tokenizer_but_keeps_sentence_end_punctuation() {
+        let mut model = NgramTrainer::new(2);
+
+        model.train_str("The Cat sat. THE Dog sat.");
+
+        // Case folded like SimpleWordTrainer's tokenizer would, but
+        // the trailing "." survives so complete_sentence can still
+        // detect where a sentence ends.
+        assert_eq!(1, *model.tables[0].get("the").unwrap().get("cat").unwrap());
+        assert!(model.tables[0].get("cat").unwrap().contains_key("sat."));
+    }
+
+    #[test]
+    fn test_predict_prefers_seen_context() {
+        let mut model = NgramTrainer::new(3);
+
+        model.train_str("a happy little dog chased a happy little cat");
+
+        let predictor = model.finalize();
+        let predictions = predictor.predict(&["a", "happy"], "l");
+
+        assert_eq!(predictions[0].word, "little");
+    }
+
+    #[test]
+    fn test_predict_scored_favors_seen_context() {
+        let mut model = NgramTrainer::new(3);
+
+        model.train_str("a happy little dog chased a happy little cat");
+
+        let predictor = model.finalize();
+        let scored = predictor.predict_scored(&["a", "happy"], "l", 1.0);
+
+        assert_eq!(scored[0].word, "little");
+        assert!(scored[0].probability > 0.0);
+    }
+
+    #[test]
+    fn test_predict_does_not_drop_context_favored_words_beyond_unigram_top_10() {
+        let mut model = NgramTrainer::new(2);
+
+        // Eleven filler words share the "l" prefix and each outrank
+        // "lobster" in raw global frequency, filling the unigram
+        // top-10 before "lobster" is even considered. Only the bigram
+        // context after "eat" singles "lobster" out.
+        model.train_str("la lb lc ld le lf lg lh li lj lk \
+                          la lb lc ld le lf lg lh li lj lk \
+                          eat lobster");
+
+        let predictor = model.finalize();
+        let predictions = predictor.predict(&["eat"], "l");
+
+        assert_eq!(predictions[0].word, "lobster");
+    }
+
+    #[test]
+    fn test_predict_scored_does_not_drop_context_favored_words_beyond_unigram_top_10() {
+        let mut model = NgramTrainer::new(2);
+
+        // Same setup as the equivalent `predict` test: eleven filler
+        // words outrank "lobster" in raw global frequency, so only
+        // scoring every prefix match before truncating keeps it in
+        // the results at all.
+        model.train_str("la lb lc ld le lf lg lh li lj lk \
+                          la lb lc ld le lf lg lh li lj lk \
+                          eat lobster");
+
+        let predictor = model.finalize();
+        let scored = predictor.predict_scored(&["eat"], "l", 1.0);
+
+        assert_eq!(scored[0].word, "lobster");
+    }
+
+    #[test]
+    fn test_predict_backs_off_to_unigram_on_unseen_context() {
+        let mut model = NgramTrainer::new(3);
+
+        // "cat" is trained twice and "chased" only once, so the
+        // unigram fallback ranks them deterministically instead of
+        // relying on HashMap iteration order to break a tie.
+        model.train_str("a happy little dog chased a happy little cat and the cat slept");
+
+        let predictor = model.finalize();
+        let predictions = predictor.predict(&["never", "seen"], "c");
+
+        assert_eq!(predictions[0].word, "cat");
+    }
+
+    #[test]
+    fn test_complete_sentence_stops_at_sentence_ending_token() {
+        let mut model = NgramTrainer::new(2);
+
+        model.train_str("the cat sat. the dog sat.");
+
+        let predictor = model.finalize();
+        let completions = predictor.complete_sentence("cat", 1, 5);
+
+        assert_eq!(completions[0].0, "sat.");
+    }
+
+    #[test]
+    fn test_complete_sentence_stops_at_max_len() {
+        let mut model = NgramTrainer::new(2);
+
+        model.train_str("a b a b a b");
+
+        let predictor = model.finalize();
+        let completions = predictor.complete_sentence("a", 1, 2);
+
+        assert_eq!(completions[0].0, "b a");
+    }
+
+    #[test]
+    fn test_complete_sentence_sorts_by_log_probability_descending() {
+        let mut model = NgramTrainer::new(2);
+
+        model.train_str("the cat sat. the dog sat. a bird flew.");
+
+        let predictor = model.finalize();
+        let completions = predictor.complete_sentence("the", 2, 5);
+
+        for pair in completions.windows(2) {
+            assert!(pair[0].1 >= pair[1].1);
+        }
+    }
+}
@@ -39,3 +39,14 @@ impl Ord for PredictionEntry {
         self.word.cmp(&other.word)
     }
 }
+
+/// Entry returned by `predict_scored`. Carries the raw `score` count
+/// alongside a smoothed `probability` so callers can compare
+/// candidates drawn from contexts of different sizes, which raw
+/// counts can't do.
+#[derive(Debug, Clone)]
+pub struct ScoredPredictionEntry {
+    pub word: String,
+    pub score: u32,
+    pub probability: f64,
+}
@@ -1,4 +1,5 @@
-use predictionentry::PredictionEntry;
+use predictionentry::{PredictionEntry, ScoredPredictionEntry};
+use tokenizer::{Tokenizer, DefaultTokenizer};
 
 use std::old_io::File;
 use std::old_io::BufferedReader;
@@ -10,183 +11,546 @@ use std::collections::hash_map::Entry;
 ///
 /// SimpleWordTrainer uses a HashMap representation to train on word
 /// frequency. It is fast for looking up words and incrementing their
-/// count but since HashMap does not keep track of order, searching the
-/// keys of a hashmap takes a long time. After the model is trained it
-/// must be converted to a SimpleWordPredictor which stores the words
-/// in lexical order and has an index of first letters.
-#[derive(Debug)]
-pub struct SimpleWordTrainer(HashMap<String, u32>);
+/// count. After the model is trained it must be converted to a
+/// SimpleWordPredictor, which arranges the words into a prefix trie.
+/// Raw input passed to `from_str`, `train_str` and `train_vec` is
+/// split into words by a `Tokenizer`, which defaults to
+/// `DefaultTokenizer` but can be swapped via `with_tokenizer` to
+/// control case folding, punctuation, script handling and stop words.
+pub struct SimpleWordTrainer {
+    counts: HashMap<String, u32>,
+    tokenizer: Box<Tokenizer>,
+}
 
 /// Single-word prediction engine
 ///
-/// SimpleWordPredictor uses a constant sized vector of entries indexed by
-/// first letter. Prediction starts from that index and continues until
-/// the first letter in the vector changes.
+/// SimpleWordPredictor stores its entries in a prefix trie: each node
+/// holds a map of child nodes keyed by the next character, plus an
+/// optional score for words that terminate there. Looking up a prefix
+/// walks one node per input character, so lookup cost no longer grows
+/// with the size of the vocabulary.
 #[derive(Debug)]
 pub struct SimpleWordPredictor {
-    entries: Vec<PredictionEntry>,
-    ixs: HashMap<char, u32>,
+    root: Node,
+    // Opt-in rules consulted by `predict`: a query word expands into
+    // its synonyms (e.g. "nyc" -> "new york") and each variant is
+    // looked up too, merging results by max score.
+    synonyms: HashMap<String, Vec<String>>,
+}
+
+#[derive(Debug)]
+struct Node {
+    children: HashMap<char, Box<Node>>,
+    score: Option<u32>,
+}
+
+impl Node {
+    fn new() -> Node {
+        Node {children: HashMap::new(), score: None}
+    }
 }
 
 impl SimpleWordPredictor {
-    /// Given an initial input string, return 10 predictions.
+    /// Given an initial input string, return up to 10 predictions:
+    /// exact-prefix trie matches, plus (if configured) matches for
+    /// any synonym of `input`, plus split/concatenation candidates —
+    /// trying `input` itself as one word, and each point where it
+    /// might be two trained words run together. All of these are
+    /// merged by taking the max score per word before truncating.
     pub fn predict(&self, input: &str) -> Vec<PredictionEntry> {
-        let mut predictions: Vec<PredictionEntry> = Vec::new();
-        let iter = self.entries.iter();
-        let first_letter = input.char_at(0);
-        let skip_n = self.ixs.get(&first_letter);
-
-        match skip_n {
-            Some(n) => {
-                let iter = iter.skip(*n as usize);
-                for entry in iter {
-                    let word = entry.word.as_slice();
-                    if word.char_at(0) != first_letter {
-                        break;
-                    }
-
-                    if word.starts_with(input) {
-                        predictions.push(entry.clone());
-                    }
+        let mut merged: HashMap<String, u32> = HashMap::new();
+
+        for entry in self.predict_prefix(input) {
+            merge_max(&mut merged, entry.word, entry.score);
+        }
+
+        if let Some(synonyms) = self.synonyms.get(input) {
+            for synonym in synonyms {
+                for entry in self.predict_prefix(synonym) {
+                    merge_max(&mut merged, entry.word, entry.score);
                 }
+            }
+        }
+
+        // Concatenation: the user may have typed two space-separated
+        // tokens that were trained as one word ("web site" ->
+        // "website"); if joining `input`'s tokens together is itself
+        // a trained word, surface it under that joined spelling. The
+        // trie never stores whitespace, so this only ever matches
+        // once the tokens are joined.
+        let joined: String = input.chars().filter(|c| !c.is_whitespace()).collect();
+        if let Some(score) = self.score_of(&joined) {
+            merge_max(&mut merged, joined, score);
+        }
+
+        // Splitting: the reverse case. If `input` is itself two
+        // trained words run together ("nycweather"), try every split
+        // point and surface the first half whenever both halves are
+        // trained words.
+        let chars: Vec<char> = input.chars().collect();
+        for split in 1..chars.len() {
+            let first: String = chars[..split].iter().cloned().collect();
+            let second: String = chars[split..].iter().cloned().collect();
+            if let (Some(first_score), Some(_)) = (self.score_of(&first), self.score_of(&second)) {
+                merge_max(&mut merged, first, first_score);
+            }
+        }
+
+        let mut predictions: Vec<PredictionEntry> = merged.into_iter()
+            .map(|(word, score)| PredictionEntry {word: word, score: score})
+            .collect();
+
+        predictions.sort_by(|a, b| {
+            b.score.cmp(&a.score)
+        });
+
+        predictions.truncate(10);
+
+        predictions
+    }
+
+    /// The plain trie prefix lookup `predict` is built on, with no
+    /// synonym or split/concat rules applied, truncated to the top 10
+    /// by raw frequency.
+    fn predict_prefix(&self, input: &str) -> Vec<PredictionEntry> {
+        let mut predictions = self.predict_prefix_all(input);
+        predictions.truncate(10);
+
+        predictions
+    }
+
+    /// Every trie entry whose spelling starts with `input`, sorted by
+    /// raw frequency but *not* truncated. Callers that only want raw
+    /// frequency order should use `predict` instead; this exists for
+    /// callers (e.g. `NgramPredictor`) that re-rank candidates by a
+    /// different score and must not lose candidates to a truncation
+    /// that happened before their own ranking was applied.
+    pub fn predict_prefix_all(&self, input: &str) -> Vec<PredictionEntry> {
+        let mut node = &self.root;
+        for c in input.chars() {
+            match node.children.get(&c) {
+                Some(child) => node = child,
+                None => return Vec::new(),
+            }
+        }
+
+        let mut predictions = Vec::new();
+        let mut buf: Vec<char> = input.chars().collect();
+        walk(node, &mut buf, &mut |word, score| {
+            predictions.push(PredictionEntry {word: String::from_str(word), score: score});
+        });
+
+        predictions.sort_by(|a, b| {
+            b.score.cmp(&a.score)
+        });
+
+        predictions
+    }
+
+    /// Attach a synonym table so `predict` also expands a query word
+    /// into its synonyms. Opt-in: predictors have no synonyms until
+    /// this (or `load_synonyms`) is called.
+    pub fn with_synonyms(mut self, synonyms: HashMap<String, Vec<String>>) -> SimpleWordPredictor {
+        self.synonyms = synonyms;
+        self
+    }
+
+    /// Load synonym pairs from a CSV file alongside the trained
+    /// model: each row is `word,synonym`; multiple rows for the same
+    /// word accumulate into its synonym list.
+    pub fn load_synonyms(&mut self, path: &Path) {
+        let mut file = BufferedReader::new(File::open(path));
+        for line_res in file.lines() {
+            let line = line_res.unwrap();
+            let line = line.trim();
+            let fields: Vec<&str> = line.split(',').collect();
+            let word = String::from_str(fields[0]);
+            let synonym = String::from_str(fields[1]);
+            self.synonyms.entry(word).or_insert_with(Vec::new).push(synonym);
+        }
+    }
+
+    /// Like `predict`, but ranks by a smoothed probability instead of
+    /// a raw count. Uses add-k (Laplace when `k == 1.0`) smoothing:
+    /// a candidate seen `count` times out of `total` trained words
+    /// across a vocabulary of `vocab` distinct words gets probability
+    /// `(count + k) / (total + k * vocab)`, so unseen words never
+    /// collapse to zero and contexts of different sizes stay
+    /// comparable.
+    pub fn predict_scored(&self, input: &str, k: f64) -> Vec<ScoredPredictionEntry> {
+        let total = self.total() as f64;
+        let vocab = self.scores().len() as f64;
+
+        let mut predictions: Vec<ScoredPredictionEntry> = self.predict(input).into_iter()
+            .map(|entry| {
+                let probability = (entry.score as f64 + k) / (total + k * vocab);
+                ScoredPredictionEntry {word: entry.word, score: entry.score, probability: probability}
+            })
+            .collect();
+
+        predictions.sort_by(|a, b| b.probability.partial_cmp(&a.probability).unwrap());
+
+        predictions
+    }
+
+    /// Typo-tolerant prediction: walk the trie alongside a
+    /// Levenshtein automaton for `input` bounded by edit distance
+    /// `max_dist`, so a misspelled word like "teh" can still surface
+    /// "the". A trained word matches only when its whole spelling is
+    /// within `max_dist` edits of `input`; longer words that merely
+    /// start with `input` are not matched unless they are that close.
+    /// Matches are scored by the stored frequency divided by one plus
+    /// the edit distance, so an exact match keeps its full score and
+    /// corrections are penalized.
+    pub fn predict_fuzzy(&self, input: &str, max_dist: u32) -> Vec<PredictionEntry> {
+        let automaton = LevenshteinAutomaton::new(input, max_dist);
+        let start_state = automaton.start();
+
+        let mut predictions = Vec::new();
+        let mut buf = Vec::new();
+        fuzzy_collect(&self.root, &automaton, start_state, &mut buf, &mut predictions);
+
+        predictions.sort_by(|a, b| {
+            b.score.cmp(&a.score)
+        });
+
+        predictions.truncate(10);
+
+        predictions
+    }
+
+    /// Visit every terminal word in the trie, in pre-order, calling
+    /// `f` with the reconstructed word and its score. This is the
+    /// building block `scores`, `total` and CSV export are all
+    /// written in terms of.
+    pub fn for_each_recursive<F>(&self, mut f: F) where F: FnMut(&str, u32) {
+        let mut buf = Vec::new();
+        walk(&self.root, &mut buf, &mut f);
+    }
 
-                predictions.sort_by(|a, b| {
-                    b.score.cmp(&a.score)
-                });
+    /// Return every trained entry, sorted lexically by word.
+    pub fn scores(&self) -> Vec<PredictionEntry> {
+        let mut entries = Vec::new();
+        self.for_each_recursive(|word, score| {
+            entries.push(PredictionEntry {word: String::from_str(word), score: score});
+        });
+
+        entries.sort();
 
-                predictions.truncate(10);
+        entries
+    }
 
-                predictions
-            },
-            None => {
-                return predictions;
-            },
+    /// Look up the trained score for a single, fully-spelled word.
+    pub fn score_of(&self, word: &str) -> Option<u32> {
+        let mut node = &self.root;
+        for c in word.chars() {
+            match node.children.get(&c) {
+                Some(child) => node = child,
+                None => return None,
+            }
         }
+
+        node.score
+    }
+
+    /// Sum of every trained word's score, used to turn raw counts
+    /// into frequencies.
+    pub fn total(&self) -> u32 {
+        self.scores().iter().map(|e| e.score).sum()
     }
 
     /// Load training data from a CSV file.
     pub fn from_file(path: &Path) -> SimpleWordPredictor {
-        let mut entries = Vec::new();
+        let mut root = Node::new();
         let mut file = BufferedReader::new(File::open(path));
         for line_res in file.lines() {
             let line = line_res.unwrap();
             let line = line.trim();
             let str_entry: Vec<&str> = line.split(',').collect();
-            let word: String = String::from_str(str_entry[0]);
+            let word = str_entry[0];
             let n = str_entry[1].parse().ok().unwrap();
-            entries.push(PredictionEntry {word: word, score: n});
+            insert(&mut root, word, n);
         }
 
-        let ixs = generate_ixs(&entries);
-
-        SimpleWordPredictor {entries: entries, ixs: ixs}
+        SimpleWordPredictor {root: root, synonyms: HashMap::new()}
     }
 
     /// Save training data to a CSV file.
     pub fn to_file(&self, path: &Path) {
         let mut file = File::create(path);
-        for entry in &self.entries {
+        for entry in self.scores() {
             write!(&mut file, "{},{}\n", &entry.word, entry.score)
                 .unwrap();
         }
     }
+
+    /// Record that `word` was chosen, incrementing its score (or
+    /// inserting it with a score of 1 if it hasn't been seen before)
+    /// without rebuilding the trie from scratch.
+    pub fn observe(&mut self, word: &str) {
+        let mut node = &mut self.root;
+        for c in word.chars() {
+            node = &mut **node.children.entry(c).or_insert_with(|| Box::new(Node::new()));
+        }
+        node.score = Some(node.score.unwrap_or(0) + 1);
+    }
 }
 
 impl SimpleWordTrainer {
     /// Create a trainer trained on an initial str.
     pub fn from_str(input: &str) -> SimpleWordTrainer {
-        let model_hm: HashMap<String, u32> = HashMap::new();
-        let mut model = SimpleWordTrainer(model_hm);
-        let v_input = input.split(' ').collect();
-        count_words(&mut model, &v_input);
+        let mut model = SimpleWordTrainer::new();
+        model.train_str(input);
 
         model
     }
 
     /// Create a trainer trained on an initial vector strs.
     pub fn from_vec(input: &Vec<&str>) -> SimpleWordTrainer {
-        let model_hm: HashMap<String, u32> = HashMap::new();
-        let mut model = SimpleWordTrainer(model_hm);
-
-        count_words(&mut model, input);
+        let mut model = SimpleWordTrainer::new();
+        model.train_vec(input.clone());
 
         model
     }
 
-    /// Create a new untrained trainer.
+    /// Create a new untrained trainer using the default tokenizer.
     pub fn new() -> SimpleWordTrainer {
-        let model_hm: HashMap<String, u32> = HashMap::new();
+        SimpleWordTrainer::with_tokenizer(Box::new(DefaultTokenizer::new()))
+    }
 
-        SimpleWordTrainer(model_hm)
+    /// Create a new untrained trainer that tokenizes input with a
+    /// caller-supplied `Tokenizer`, for controlling language-specific
+    /// behavior (case folding, punctuation, CJK splitting, stop
+    /// words) instead of `DefaultTokenizer`.
+    pub fn with_tokenizer(tokenizer: Box<Tokenizer>) -> SimpleWordTrainer {
+        SimpleWordTrainer {counts: HashMap::new(), tokenizer: tokenizer}
     }
 
-    /// Train the model on a string that will be split on spaces.
+    /// Train the model on a string, tokenized by `self.tokenizer`.
     pub fn train_str(&mut self, input: &str) {
-        let v_input = input.split(' ').collect();
-        count_words(self, &v_input);
+        for word in self.tokenizer.tokenize(input) {
+            train_single_word(&mut self.counts, &word);
+        }
     }
 
-    /// Train the model on a vector of words.
+    /// Train the model on a vector of words, tokenized by
+    /// `self.tokenizer` as if they were joined back into one string.
     pub fn train_vec(&mut self, input: Vec<&str>) {
-        count_words(self, &input);
+        self.train_str(&input.join(" "));
     }
 
+    /// Train on a single word, bypassing the tokenizer. Used when the
+    /// caller already has a clean, individual token (e.g. the
+    /// bigram trainer recording one half of a word pair).
     pub fn train_word(&mut self, input: &str) {
-        let &mut SimpleWordTrainer(ref mut model_hm) = self;
-        train_single_word(model_hm, input);
+        train_single_word(&mut self.counts, input);
     }
 
     /// Perform the calculations needed to predict the next word.
     pub fn finalize(self) -> SimpleWordPredictor {
-        let SimpleWordTrainer(hm) = self;
-        let size = hm.len();
-        let mut entries = Vec::with_capacity(size);
+        let mut root = Node::new();
 
-        for (key, value) in hm {
-            entries.push(PredictionEntry {word: key, score: value});
+        for (word, score) in self.counts {
+            insert(&mut root, &word, score);
         }
 
-        entries.sort();
-
-        let ixs = generate_ixs(&entries);
-        SimpleWordPredictor {entries: entries, ixs: ixs}
+        SimpleWordPredictor {root: root, synonyms: HashMap::new()}
     }
 
     /// This method is not meant to be called from outside the library.
     pub fn debug_get_word_score(&self, word: &str) -> Option<&u32> {
-        let &SimpleWordTrainer(ref hm) = self;
+        self.counts.get(word)
+    }
+
+}
+
+fn insert(root: &mut Node, word: &str, score: u32) {
+    let mut node = root;
+    for c in word.chars() {
+        node = &mut **node.children.entry(c).or_insert_with(|| Box::new(Node::new()));
+    }
+    node.score = Some(score);
+}
+
+// Pre-order walk of the trie rooted at `node`, calling `f` with each
+// terminal word (the prefix already in `buf` plus the path walked
+// since) and its score.
+fn walk<F>(node: &Node, buf: &mut Vec<char>, f: &mut F) where F: FnMut(&str, u32) {
+    if let Some(score) = node.score {
+        let word: String = buf.iter().cloned().collect();
+        f(&word, score);
+    }
+
+    for (&c, child) in &node.children {
+        buf.push(c);
+        walk(child, buf, f);
+        buf.pop();
+    }
+}
+
+// A precomputed DFA for bounded-edit-distance matching against a
+// fixed query string: a "state" is the DP row tracking the edit
+// distance from every prefix of the query to the path walked so far
+// (so state `s` encodes the `(offset, errors)` position the walk is
+// at, for every offset into the query at once). Rather than
+// recomputing that row's recurrence at every trie edge, `new` walks
+// every state reachable from the start row and records, for every
+// possible *characteristic vector* of the next input character (the
+// bitmask of which query positions it matches), which state that
+// transition lands on. Characters themselves are never part of the
+// table: two different characters that agree on which query
+// positions they match are interchangeable as far as the row
+// recurrence is concerned, so the table's second axis is the
+// bitmask, not the alphabet. `step` and `is_accepting` then do a
+// single array lookup per input character instead of walking the DP
+// recurrence.
+struct LevenshteinAutomaton {
+    query: Vec<char>,
+    max_dist: u32,
+    // states[s] is the DP row for state `s`; transitions[s][mask] is
+    // the state reached from `s` on a character whose characteristic
+    // vector (against `query`) is `mask`, or None if every entry of
+    // the resulting row would exceed `max_dist`.
+    states: Vec<Vec<u32>>,
+    transitions: Vec<Vec<Option<usize>>>,
+}
+
+impl LevenshteinAutomaton {
+    fn new(query: &str, max_dist: u32) -> LevenshteinAutomaton {
+        let query: Vec<char> = query.chars().collect();
+        let qlen = query.len();
+        let num_masks = 1usize << qlen;
+
+        let start_row: Vec<u32> = (0..qlen as u32 + 1).collect();
+
+        let mut states: Vec<Vec<u32>> = vec![start_row.clone()];
+        let mut index: HashMap<Vec<u32>, usize> = HashMap::new();
+        index.insert(start_row, 0);
+
+        let mut transitions: Vec<Vec<Option<usize>>> = Vec::new();
+        let mut frontier = 0;
+
+        while frontier < states.len() {
+            let row = states[frontier].clone();
+            let mut row_transitions = vec![None; num_masks];
+
+            for mask in 0..num_masks {
+                if let Some(next_row) = step_row(&row, qlen, mask, max_dist) {
+                    let next_id = match index.get(&next_row) {
+                        Some(&id) => id,
+                        None => {
+                            let id = states.len();
+                            states.push(next_row.clone());
+                            index.insert(next_row, id);
+                            id
+                        }
+                    };
+                    row_transitions[mask] = Some(next_id);
+                }
+            }
+
+            transitions.push(row_transitions);
+            frontier += 1;
+        }
+
+        LevenshteinAutomaton {query: query, max_dist: max_dist, states: states, transitions: transitions}
+    }
+
+    fn start(&self) -> usize {
+        0
+    }
+
+    // The characteristic vector of `c` against the query: bit `j` is
+    // set when `query[j] == c`. This is the only place an actual
+    // character is examined; everything downstream is a table lookup
+    // keyed on the resulting bitmask.
+    fn characteristic_vector(&self, c: char) -> usize {
+        let mut mask = 0usize;
+        for (j, &qc) in self.query.iter().enumerate() {
+            if qc == c {
+                mask |= 1 << j;
+            }
+        }
+        mask
+    }
+
+    // Transition from `state` on character `c`: a single precomputed
+    // table lookup, no DP recurrence at call time.
+    fn step(&self, state: usize, c: char) -> Option<usize> {
+        let mask = self.characteristic_vector(c);
+        self.transitions[state][mask]
+    }
 
-        hm.get(word)
+    fn distance(&self, state: usize) -> u32 {
+        self.states[state][self.query.len()]
     }
 
+    fn is_accepting(&self, state: usize) -> bool {
+        self.distance(state) <= self.max_dist
+    }
 }
 
-fn generate_ixs(entries: &Vec<PredictionEntry>) -> HashMap<char, u32>{
-    let mut ixs: HashMap<char, u32> = HashMap::new();
+// The DP row recurrence itself, expressed in terms of a
+// characteristic vector rather than a literal character so it can be
+// run once per (state, mask) pair while building the transition
+// table instead of once per (state, character) pair encountered
+// during a trie walk. Returns None once every entry of the resulting
+// row already exceeds `max_dist`, since no further input can bring
+// such a row back under budget — callers can prune that state
+// entirely.
+fn step_row(row: &[u32], qlen: usize, mask: usize, max_dist: u32) -> Option<Vec<u32>> {
+    let mut next = Vec::with_capacity(row.len());
+    next.push(row[0] + 1);
+
+    for j in 0..qlen {
+        let cost = if (mask >> j) & 1 == 1 { 0 } else { 1 };
+        let value = std::cmp::min(next[j] + 1, std::cmp::min(row[j + 1] + 1, row[j] + cost));
+        next.push(value);
+    }
+
+    if *next.iter().min().unwrap() > max_dist {
+        None
+    } else {
+        Some(next)
+    }
+}
 
-    // There will be no newlines in the input. This is a placeholder
-    // until the first time the loop runs.
-    let mut last_c = '\n';
-    let mut ix = 0;
+fn fuzzy_collect(node: &Node, automaton: &LevenshteinAutomaton, state: usize,
+                 buf: &mut Vec<char>, out: &mut Vec<PredictionEntry>) {
+    if let Some(score) = node.score {
+        if automaton.is_accepting(state) {
+            let word: String = buf.iter().cloned().collect();
+            let penalized = score / (1 + automaton.distance(state));
+            out.push(PredictionEntry {word: word, score: penalized});
+        }
+    }
 
-    for entry in entries {
-        let c = entry.word.char_at(0);
-        if c != last_c {
-            ixs.insert(c, ix);
-            last_c = c;
+    for (&c, child) in &node.children {
+        if let Some(next_state) = automaton.step(state, c) {
+            buf.push(c);
+            fuzzy_collect(child, automaton, next_state, buf, out);
+            buf.pop();
         }
-        ix += 1;
     }
+}
 
-    ixs
+// Insert `(word, score)` into `merged`, keeping the higher score if
+// the word is already present. Used to combine candidates drawn from
+// several rules (prefix match, synonym expansion, split/concat)
+// without letting a rule stack scores for the same word.
+fn merge_max(merged: &mut HashMap<String, u32>, word: String, score: u32) {
+    let entry = merged.entry(word).or_insert(score);
+    if score > *entry {
+        *entry = score;
+    }
 }
 
-fn train_single_word(model_hm: &mut HashMap<String, u32>, word: &str) {
+fn train_single_word(counts: &mut HashMap<String, u32>, word: &str) {
     if word.len() == 0 {
         return;
     }
     let string_word = String::from_str(word);
-    let entry = model_hm.entry(string_word);
+    let entry = counts.entry(string_word);
     match entry {
         Entry::Vacant(vacant_entry) => {
             vacant_entry.insert(1);
@@ -198,17 +562,11 @@ fn train_single_word(model_hm: &mut HashMap<String, u32>, word: &str) {
     }
 }
 
-fn count_words(model: &mut SimpleWordTrainer, input: &Vec<&str>) {
-    let &mut SimpleWordTrainer(ref mut model_hm) = model;
-    for word in input {
-        train_single_word(model_hm, word);
-    }
-}
-
 #[cfg(test)]
 mod tests {
     use simplemodel::SimpleWordTrainer;
     use predictionentry::PredictionEntry;
+    use tokenizer::DefaultTokenizer;
 
     #[test]
     fn test_debug() {
@@ -221,20 +579,16 @@ mod tests {
     fn test_from_str() {
         let model = SimpleWordTrainer::from_str("world domination is my profession hello hello");
 
-        let SimpleWordTrainer(hash_map) = model;
-
-        assert_eq!(1, *hash_map.get("world").unwrap());
-        assert_eq!(2, *hash_map.get("hello").unwrap());
+        assert_eq!(&1, model.debug_get_word_score("world").unwrap());
+        assert_eq!(&2, model.debug_get_word_score("hello").unwrap());
     }
 
     #[test]
     fn test_from_vec() {
         let model = SimpleWordTrainer::from_vec(&vec!["rabbit", "rabbit", "hare"]);
 
-        let SimpleWordTrainer(hash_map) = model;
-
-        assert_eq!(1, *hash_map.get("hare").unwrap());
-        assert_eq!(2, *hash_map.get("rabbit").unwrap());
+        assert_eq!(&1, model.debug_get_word_score("hare").unwrap());
+        assert_eq!(&2, model.debug_get_word_score("rabbit").unwrap());
     }
 
     #[test]
@@ -243,9 +597,8 @@ mod tests {
 
         model.train_str("hello hello hello there there");
 
-        let SimpleWordTrainer(hash_map) = model;
-        assert_eq!(3, *hash_map.get("hello").unwrap());
-        assert_eq!(2, *hash_map.get("there").unwrap());
+        assert_eq!(&3, model.debug_get_word_score("hello").unwrap());
+        assert_eq!(&2, model.debug_get_word_score("there").unwrap());
     }
 
     #[test]
@@ -255,9 +608,20 @@ mod tests {
         model.train_vec(vec!["hello", "hello", "hello", "what",
                              "is", "this"]);
 
-        let SimpleWordTrainer(hash_map) = model;
-        assert_eq!(3, *hash_map.get("hello").unwrap());
-        assert_eq!(1, *hash_map.get("what").unwrap());
+        assert_eq!(&3, model.debug_get_word_score("hello").unwrap());
+        assert_eq!(&1, model.debug_get_word_score("what").unwrap());
+    }
+
+    #[test]
+    fn test_train_str_uses_custom_tokenizer() {
+        let mut model = SimpleWordTrainer::with_tokenizer(
+            Box::new(DefaultTokenizer::with_stop_words(&["the"])));
+
+        model.train_str("The Cat sat on the mat.");
+
+        assert_eq!(model.debug_get_word_score("the"), None);
+        assert_eq!(&1, model.debug_get_word_score("cat").unwrap());
+        assert_eq!(&1, model.debug_get_word_score("mat").unwrap());
     }
 
     #[test]
@@ -271,9 +635,10 @@ mod tests {
                                 "power and is not easy"]);
 
         let predictor = model.finalize();
+        let scores = predictor.scores();
+        let and_entry = scores.iter().find(|e| e.word == "and").unwrap();
 
-        assert_eq!(predictor.entries[0].word, "and");
-        assert_eq!(predictor.entries[0].score, 5);
+        assert_eq!(and_entry.score, 5);
     }
 
     #[test]
@@ -289,7 +654,150 @@ mod tests {
         let predictor = model.finalize();
         let prediction = predictor.predict("a");
 
-        assert_eq!(prediction[0], PredictionEntry {word: String::from_str("and"), score: 4});
+        assert_eq!(prediction[0], PredictionEntry {word: String::from_str("and"), score: 5});
+    }
+
+    #[test]
+    fn test_predict_fuzzy() {
+        let mut model = SimpleWordTrainer::new();
+        model.train_str("the the the technology teh");
+
+        let predictor = model.finalize();
+        let prediction = predictor.predict_fuzzy("teh", 2);
+
+        assert!(prediction.iter().any(|e| e.word == "the"));
+        assert!(prediction.iter().any(|e| e.word == "teh"));
+
+        // An exact match should still rank at least as high as a
+        // two-edit correction with three times its raw frequency.
+        let exact = prediction.iter().find(|e| e.word == "teh").unwrap();
+        let corrected = prediction.iter().find(|e| e.word == "the").unwrap();
+        assert!(exact.score >= corrected.score);
+    }
+
+    #[test]
+    fn test_predict_scored() {
+        let mut model = SimpleWordTrainer::new();
+        model.train_str("cat cat dog");
+
+        let predictor = model.finalize();
+        let scored = predictor.predict_scored("c", 1.0);
+
+        // total = 3, vocab = 2, so "cat" (count 2) gets (2+1)/(3+2) = 0.6.
+        let cat = scored.iter().find(|e| e.word == "cat").unwrap();
+        assert!((cat.probability - 0.6).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_observe_updates_existing_word_without_rebuilding() {
+        let mut model = SimpleWordTrainer::new();
+        model.train_str("hello hello world");
+
+        let mut predictor = model.finalize();
+        predictor.observe("hello");
+        predictor.observe("brand");
+
+        assert_eq!(predictor.score_of("hello"), Some(3));
+        assert_eq!(predictor.score_of("brand"), Some(1));
+    }
+
+    #[test]
+    fn test_for_each_recursive_visits_every_word() {
+        let mut model = SimpleWordTrainer::new();
+        model.train_str("cat cat dog");
+
+        let predictor = model.finalize();
+        let mut seen = Vec::new();
+        predictor.for_each_recursive(|word, score| {
+            seen.push((String::from_str(word), score));
+        });
+
+        seen.sort();
+        assert_eq!(seen, vec![
+            (String::from_str("cat"), 2),
+            (String::from_str("dog"), 1),
+        ]);
+    }
+
+    #[test]
+    fn test_predict_fuzzy_rejects_beyond_max_dist() {
+        let mut model = SimpleWordTrainer::new();
+        model.train_str("the");
+
+        let predictor = model.finalize();
+
+        // "xyz" is edit distance 3 from "the"; distance 1 shouldn't
+        // find it at all.
+        assert_eq!(predictor.predict_fuzzy("xyz", 1).len(), 0);
+        assert!(predictor.predict_fuzzy("xyz", 3).iter().any(|e| e.word == "the"));
+    }
+
+    #[test]
+    fn test_predict_prefix_all_is_not_truncated() {
+        let mut model = SimpleWordTrainer::new();
+        model.train_str("la lb lc ld le lf lg lh li lj lk lm");
+
+        let predictor = model.finalize();
+
+        assert_eq!(predictor.predict("l").len(), 10);
+        assert_eq!(predictor.predict_prefix_all("l").len(), 12);
+    }
+
+    #[test]
+    fn test_predict_missing_prefix() {
+        let mut model = SimpleWordTrainer::new();
+        model.train_str("hello world");
+
+        let predictor = model.finalize();
+
+        assert_eq!(predictor.predict("z").len(), 0);
+    }
+
+    #[test]
+    fn test_predict_expands_synonyms() {
+        use std::collections::HashMap;
+
+        let mut model = SimpleWordTrainer::new();
+        model.train_str("nyc nyc nyc big apple");
+
+        let mut synonyms = HashMap::new();
+        synonyms.insert(String::from_str("big"), vec![String::from_str("nyc")]);
+
+        let predictor = model.finalize().with_synonyms(synonyms);
+
+        // "big" has no trie matches of its own beyond the literal word,
+        // but its synonym "nyc" should be pulled in too.
+        let prediction = predictor.predict("big");
+        assert!(prediction.iter().any(|e| e.word == "nyc"));
+    }
+
+    #[test]
+    fn test_predict_splits_concatenated_words() {
+        let mut model = SimpleWordTrainer::new();
+        model.train_str("web site website");
+
+        let predictor = model.finalize();
+
+        // Splitting: "website" is also "web" + "site" run together,
+        // and both halves are trained words, so besides the prefix
+        // match for "website" the split rule should surface "web".
+        let split = predictor.predict("website");
+        assert!(split.iter().any(|e| e.word == "web"));
+    }
+
+    #[test]
+    fn test_predict_concatenates_space_separated_tokens() {
+        let mut model = SimpleWordTrainer::new();
+        model.train_str("web site website");
+
+        let predictor = model.finalize();
+
+        // Typing "web site" with a space should still surface
+        // "website": the trie never stores whitespace, so the
+        // concatenation rule has to join the tokens before looking
+        // the joined spelling up.
+        let prediction = predictor.predict("web site");
+        assert!(prediction.iter().any(|e| e.word == "website"));
     }
 
 }
@@ -0,0 +1,155 @@
+use std::collections::HashSet;
+
+/// Turns raw input text into the word tokens a trainer counts.
+/// Implement this to control tokenization per language; the trainers'
+/// `with_tokenizer` constructors accept any `Tokenizer`, and their
+/// `train_str` and `train_vec` run input through it, so callers aren't
+/// stuck with `DefaultTokenizer` (which `from_str` always uses).
+pub trait Tokenizer {
+    fn tokenize(&self, input: &str) -> Vec<String>;
+}
+
+/// The default tokenizer: lowercases, strips surrounding punctuation,
+/// folds common accented Latin letters to their plain ASCII form,
+/// splits runs of CJK characters into individual codepoint tokens
+/// (those scripts don't separate words with spaces), and drops a
+/// configurable stop-word set.
+pub struct DefaultTokenizer {
+    stop_words: HashSet<String>,
+}
+
+impl DefaultTokenizer {
+    /// A tokenizer with no stop words.
+    pub fn new() -> DefaultTokenizer {
+        DefaultTokenizer {stop_words: HashSet::new()}
+    }
+
+    /// A tokenizer that additionally drops every word in `stop_words`.
+    pub fn with_stop_words(stop_words: &[&str]) -> DefaultTokenizer {
+        DefaultTokenizer {
+            stop_words: stop_words.iter().map(|w| String::from_str(*w)).collect(),
+        }
+    }
+}
+
+impl Tokenizer for DefaultTokenizer {
+    fn tokenize(&self, input: &str) -> Vec<String> {
+        let mut words = Vec::new();
+
+        for raw in input.split(' ') {
+            let trimmed = raw.trim_matches(|c: char| !c.is_alphanumeric());
+            let folded: String = trimmed.chars()
+                .map(fold_accent)
+                .collect();
+
+            if folded.len() == 0 {
+                continue;
+            }
+
+            for word in split_cjk_runs(&folded) {
+                if !self.stop_words.contains(&word) {
+                    words.push(word);
+                }
+            }
+        }
+
+        words
+    }
+}
+
+// True for the common CJK blocks (Han ideographs, Hiragana/Katakana,
+// Hangul syllables), which don't use whitespace between words.
+fn is_cjk(c: char) -> bool {
+    let n = c as u32;
+    (n >= 0x4E00 && n <= 0x9FFF) ||
+    (n >= 0x3040 && n <= 0x30FF) ||
+    (n >= 0xAC00 && n <= 0xD7A3)
+}
+
+fn fold_accent(c: char) -> char {
+    let c = c.to_lowercase().next().unwrap_or(c);
+    match c {
+        'à' | 'á' | 'â' | 'ã' | 'ä' | 'å' => 'a',
+        'è' | 'é' | 'ê' | 'ë' => 'e',
+        'ì' | 'í' | 'î' | 'ï' => 'i',
+        'ò' | 'ó' | 'ô' | 'õ' | 'ö' => 'o',
+        'ù' | 'ú' | 'û' | 'ü' => 'u',
+        'ñ' => 'n',
+        'ç' => 'c',
+        other => other,
+    }
+}
+
+// A script without spaces degenerates to one giant token if left
+// alone, so split every CJK codepoint out as its own word while
+// leaving runs of non-CJK characters (e.g. "hello") intact.
+fn split_cjk_runs(word: &str) -> Vec<String> {
+    let mut out = Vec::new();
+    let mut buf = String::new();
+
+    for c in word.chars() {
+        if is_cjk(c) {
+            if buf.len() > 0 {
+                out.push(buf.clone());
+                buf.clear();
+            }
+            let mut single = String::new();
+            single.push(c);
+            out.push(single);
+        } else {
+            buf.push(c);
+        }
+    }
+
+    if buf.len() > 0 {
+        out.push(buf);
+    }
+
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use tokenizer::{Tokenizer, DefaultTokenizer};
+
+    #[test]
+    fn test_lowercases_and_strips_punctuation() {
+        let tokenizer = DefaultTokenizer::new();
+
+        assert_eq!(tokenizer.tokenize("Hello, World!"),
+                   vec![String::from_str("hello"), String::from_str("world")]);
+    }
+
+    #[test]
+    fn test_keeps_interior_punctuation() {
+        let tokenizer = DefaultTokenizer::new();
+
+        assert_eq!(tokenizer.tokenize("it's a well-known co-op"),
+                   vec![String::from_str("it's"), String::from_str("a"),
+                        String::from_str("well-known"), String::from_str("co-op")]);
+    }
+
+    #[test]
+    fn test_folds_accents() {
+        let tokenizer = DefaultTokenizer::new();
+
+        assert_eq!(tokenizer.tokenize("café"), vec![String::from_str("cafe")]);
+    }
+
+    #[test]
+    fn test_splits_cjk_into_individual_codepoints() {
+        let tokenizer = DefaultTokenizer::new();
+
+        assert_eq!(tokenizer.tokenize("你好"),
+                   vec![String::from_str("你"), String::from_str("好")]);
+    }
+
+    #[test]
+    fn test_drops_stop_words() {
+        let tokenizer = DefaultTokenizer::with_stop_words(&["the", "a"]);
+
+        assert_eq!(tokenizer.tokenize("the cat sat on a mat"),
+                   vec![String::from_str("cat"), String::from_str("sat"),
+                        String::from_str("on"), String::from_str("mat")]);
+    }
+}